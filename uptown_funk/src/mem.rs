@@ -0,0 +1,115 @@
+//! Helpers shared by the code the `#[host_function]` macro generates for turning
+//! guest `(ptr, len)` pairs into host-side views of linear memory.
+
+use std::convert::TryFrom;
+use std::ops::Range;
+
+/// Converts a guest `(ptr, len)` pair into a byte range into linear memory.
+///
+/// The guest passes signed values — `i32` for 32-bit memories, `i64` for
+/// memory64 — which the generated code widens to `i64`. A malicious module can
+/// hand us a negative value or a `ptr + len` sum that overflows `usize`, so we
+/// reject negatives and values that do not fit in `usize` up front and use
+/// `checked_add`, returning `None` (which the macro turns into a trap) instead
+/// of wrapping to an in-bounds range.
+pub fn checked_range(ptr: i64, len: i64) -> Option<Range<usize>> {
+    if ptr < 0 || len < 0 {
+        return None;
+    }
+    let start = usize::try_from(ptr).ok()?;
+    let end = start.checked_add(usize::try_from(len).ok()?)?;
+    Some(start..end)
+}
+
+/// Validates a guest `(ptr, count)` pair for a slice of `elem_size`-byte
+/// elements and returns the byte range together with the element count.
+///
+/// In addition to the negative/overflow checks of [`checked_range`], the
+/// pointer must be naturally aligned for the element type (host alignment may be
+/// stricter than the guest's) and `count * elem_size` must not overflow. Returns
+/// `None` — a trap — on any violation.
+pub fn checked_elem_range(ptr: i64, count: i64, elem_size: usize) -> Option<(Range<usize>, usize)> {
+    if ptr < 0 || count < 0 || elem_size == 0 {
+        return None;
+    }
+    let start = usize::try_from(ptr).ok()?;
+    if start % elem_size != 0 {
+        return None;
+    }
+    let count = usize::try_from(count).ok()?;
+    let byte_len = count.checked_mul(elem_size)?;
+    let end = start.checked_add(byte_len)?;
+    Some((start..end, count))
+}
+
+/// Copies an owned host buffer into a caller-supplied guest buffer.
+///
+/// Returns the number of bytes written, or `None` — a trap — when the guest
+/// buffer cannot hold the whole value. Nothing is written in that case.
+pub fn copy_out(dst: &mut [u8], src: &[u8]) -> Option<usize> {
+    if dst.len() < src.len() {
+        return None;
+    }
+    dst[..src.len()].copy_from_slice(src);
+    Some(src.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_range_is_accepted() {
+        assert_eq!(checked_range(8, 4), Some(8..12));
+        assert_eq!(checked_range(0, 0), Some(0..0));
+    }
+
+    #[test]
+    fn negative_ptr_or_len_is_rejected() {
+        assert_eq!(checked_range(-1, 4), None);
+        assert_eq!(checked_range(8, -1), None);
+    }
+
+    #[test]
+    fn large_range_is_not_wrapped() {
+        // Regression: the old `(ptr + len) as usize` used i32 math, so a guest
+        // passing ptr == i32::MAX, len == 1 wrapped to a small in-bounds range.
+        // Widening to i64/usize yields the true (out-of-bounds) range instead,
+        // which the caller then rejects via `wasm_memory().get()`.
+        assert_eq!(checked_range(i32::MAX as i64, 1), Some(0x7fff_ffff..0x8000_0000));
+    }
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn overflowing_sum_is_rejected() {
+        // On a 32-bit host `start + len` can exceed `usize::MAX`; `checked_add`
+        // rejects it rather than wrapping back into bounds.
+        assert_eq!(checked_range(u32::MAX as i64, 1), None);
+    }
+
+    #[test]
+    fn elem_range_requires_alignment() {
+        assert_eq!(checked_elem_range(8, 3, 4), Some((8..20, 3)));
+        // An unaligned pointer for a 4-byte element is rejected.
+        assert_eq!(checked_elem_range(6, 3, 4), None);
+    }
+
+    #[test]
+    fn elem_range_rejects_count_overflow() {
+        assert_eq!(checked_elem_range(0, i64::MAX, 8), None);
+        assert_eq!(checked_elem_range(-1, 1, 4), None);
+    }
+
+    #[test]
+    fn copy_out_writes_when_buffer_fits() {
+        let mut dst = [0u8; 4];
+        assert_eq!(copy_out(&mut dst, b"ab"), Some(2));
+        assert_eq!(&dst, b"ab\0\0");
+    }
+
+    #[test]
+    fn copy_out_traps_when_buffer_too_small() {
+        let mut dst = [0u8; 1];
+        assert_eq!(copy_out(&mut dst, b"ab"), None);
+    }
+}
@@ -0,0 +1,112 @@
+//! Decoding of guest path bytes into an [`OsString`].
+//!
+//! WASI filesystem names are arbitrary byte strings and need not be valid UTF-8.
+//! The `#[host_function]` macro uses this module for the `&OsStr`/`&Path`
+//! transformations on targets where the bytes cannot simply be borrowed.
+
+use std::ffi::OsString;
+
+/// Decodes guest path bytes into an [`OsString`], preserving non-UTF-8 names.
+///
+/// On Unix the bytes are an `OsStr` verbatim, so they are taken as-is. On Windows
+/// the bytes are interpreted as WTF-8 and re-encoded into WTF-16, preserving
+/// unpaired surrogates rather than replacing them. On other targets the bytes
+/// are decoded as UTF-8, substituting the replacement character for ill-formed
+/// sequences.
+#[cfg(unix)]
+pub fn decode(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes.to_vec())
+}
+
+#[cfg(windows)]
+pub fn decode(bytes: &[u8]) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+    OsString::from_wide(&wtf8_to_wide(bytes))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn decode(bytes: &[u8]) -> OsString {
+    OsString::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Re-encodes WTF-8 bytes into WTF-16 code units, emitting lone surrogates
+/// unchanged and substituting U+FFFD for ill-formed byte sequences.
+#[cfg(windows)]
+fn wtf8_to_wide(bytes: &[u8]) -> Vec<u16> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let (code, len) = if byte < 0x80 {
+            (byte as u32, 1)
+        } else if byte >> 5 == 0b110 {
+            decode_continuation(bytes, i, 2, (byte & 0x1f) as u32)
+        } else if byte >> 4 == 0b1110 {
+            decode_continuation(bytes, i, 3, (byte & 0x0f) as u32)
+        } else if byte >> 3 == 0b1_1110 {
+            decode_continuation(bytes, i, 4, (byte & 0x07) as u32)
+        } else {
+            (0xFFFD, 1)
+        };
+        if code <= 0xFFFF {
+            units.push(code as u16);
+        } else {
+            let code = code - 0x1_0000;
+            units.push(0xD800 + (code >> 10) as u16);
+            units.push(0xDC00 + (code & 0x3FF) as u16);
+        }
+        i += len;
+    }
+    units
+}
+
+/// Reads the `len - 1` continuation bytes of a multi-byte sequence starting at
+/// `i`, returning the decoded code point and the consumed length, or U+FFFD and
+/// a single byte when the sequence is truncated, malformed, overlong or above
+/// U+10FFFF.
+#[cfg(windows)]
+fn decode_continuation(bytes: &[u8], i: usize, len: usize, init: u32) -> (u32, usize) {
+    let mut code = init;
+    for offset in 1..len {
+        match bytes.get(i + offset) {
+            Some(&byte) if byte & 0xC0 == 0x80 => code = (code << 6) | (byte & 0x3F) as u32,
+            _ => return (0xFFFD, 1),
+        }
+    }
+    // Overlong forms and values past U+10FFFF are ill-formed as well.
+    let min = match len {
+        2 => 0x80,
+        3 => 0x800,
+        _ => 0x1_0000,
+    };
+    if code < min || code > 0x10_FFFF {
+        return (0xFFFD, 1);
+    }
+    (code, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_utf8_round_trips() {
+        assert_eq!(decode(b"/usr/bin"), OsString::from("/usr/bin"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_preserves_non_utf8_bytes() {
+        use std::os::unix::ffi::OsStrExt;
+        let bytes = b"/tmp/\xff\xfename";
+        assert_eq!(decode(bytes).as_bytes(), bytes);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_preserves_lone_surrogate() {
+        // ED A0 80 encodes the lone high surrogate U+D800 in WTF-8.
+        assert_eq!(wtf8_to_wide(b"\xed\xa0\x80"), vec![0xD800]);
+    }
+}
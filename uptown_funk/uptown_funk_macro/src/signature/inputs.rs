@@ -1,7 +1,7 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
-use syn::{Pat, PatType, Path, Type, TypeReference};
+use syn::{Ident, Pat, PatType, Path, Type, TypeReference};
 
 use super::arg_error;
 
@@ -21,10 +21,20 @@ use super::arg_error;
 ///    ciovec structs and its length.
 /// 5. **&mut [IoSliceMut<'_>]** is split on the guest in two arguments, a pointer to a slice containing WASI
 ///    iovec structs and its length.
-/// 6. **Custom types** need to implement uptown_funk::FromWasmI32 and are created from a **i32** wasm type.
-/// 7. All other patterns will result in a compilation error.
+/// 6. **&[T] / &mut [T]** for a WASM-numeric element type (i32, i64, f32, f64, u16, u32, u64) is split into a
+///    pointer and an element count, and the little-endian bytes are decoded into a `SmallVec<[T; _]>` (and,
+///    for `&mut`, written back on drop).
+/// 7. **&OsStr / &Path** are split like `&str` but the bytes are not validated as strict UTF-8, so non-UTF-8
+///    filesystem names do not trap. They round-trip on Unix and Windows; on other targets ill-formed
+///    sequences are replaced with U+FFFD.
+/// 8. **Custom types** need to implement uptown_funk::FromWasmI32 and are created from a **i32** wasm type.
+/// 9. All other patterns will result in a compilation error.
+///
+/// `memory64` selects the guest pointer/length ABI: `i32` for a 32-bit linear
+/// memory (the default) or `i64` for modules built against the memory64 proposal.
 pub fn transform(
     pat_type: &PatType,
+    memory64: bool,
 ) -> Result<(TokenStream2, TokenStream2, TokenStream2), TokenStream> {
     let argument_name = match &*pat_type.pat {
         Pat::Ident(pat_ident) => {
@@ -84,11 +94,12 @@ pub fn transform(
         Transformation::RefStr => {
             let varname_ptr = format_ident!("{}_ptr_", argument_name);
             let varname_len = format_ident!("{}_len_", argument_name);
-            let input_argument = quote! { #varname_ptr: i32, #varname_len: i32 };
+            let int_ty = ptr_int_type(memory64);
+            let input_argument = quote! { #varname_ptr: #int_ty, #varname_len: #int_ty };
+            let range = checked_memory_range(&quote! { #varname_ptr }, &quote! { #varname_len });
             let transformation = quote! {
                 let #argument_name = {
-                    let slice = state_wrapper.wasm_memory().get(
-                        #varname_ptr as usize..(#varname_ptr + #varname_len) as usize);
+                    let slice = state_wrapper.wasm_memory().get(#range);
                     let slice = uptown_funk::Trap::try_option(slice)?;
                     let string = std::str::from_utf8(slice);
                     uptown_funk::Trap::try_result(string)?
@@ -97,15 +108,20 @@ pub fn transform(
             let host_call_argument = quote! { #argument_name };
             Ok((input_argument, transformation, host_call_argument))
         }
+        // &std::ffi::OsStr
+        Transformation::RefOsStr => Ok(transform_os_path(argument_name, false, memory64)),
+        // &std::path::Path
+        Transformation::RefPath => Ok(transform_os_path(argument_name, true, memory64)),
         // &mut [u8]
         Transformation::RefMutSlice => {
             let varname_ptr = format_ident!("{}_ptr_", argument_name);
             let varname_len = format_ident!("{}_len_", argument_name);
-            let input_argument = quote! { #varname_ptr: i32, #varname_len: i32 };
+            let int_ty = ptr_int_type(memory64);
+            let input_argument = quote! { #varname_ptr: #int_ty, #varname_len: #int_ty };
+            let range = checked_memory_range(&quote! { #varname_ptr }, &quote! { #varname_len });
             let transformation = quote! {
                 let #argument_name = {
-                    let slice = state_wrapper.wasm_memory().get_mut(
-                        #varname_ptr as usize..(#varname_ptr + #varname_len) as usize);
+                    let slice = state_wrapper.wasm_memory().get_mut(#range);
                     uptown_funk::Trap::try_option(slice)?
                 };
             };
@@ -116,18 +132,19 @@ pub fn transform(
         Transformation::RefSliceIoSlices => {
             let varname_ptr = format_ident!("{}_ptr_", argument_name);
             let varname_len = format_ident!("{}_len_", argument_name);
-            let input_argument = quote! { #varname_ptr: i32, #varname_len: i32 };
+            let int_ty = ptr_int_type(memory64);
+            let input_argument = quote! { #varname_ptr: #int_ty, #varname_len: #int_ty };
+            let range = checked_memory_range(&quote! { #varname_ptr }, &quote! { #varname_len });
+            let io_vec_range = checked_memory_range(&quote! { io_vec_t.ptr }, &quote! { io_vec_t.len });
             let transformation = quote! {
                 let #argument_name = {
-                    let slice = state_wrapper.wasm_memory().get(
-                        #varname_ptr as usize..(#varname_ptr + #varname_len) as usize);
+                    let slice = state_wrapper.wasm_memory().get(#range);
                     let slice = uptown_funk::Trap::try_option(slice)?;
                     let io_slices: &[uptown_funk::IoVecT] = unsafe { std::mem::transmute(slice) };
                     // If we only need 4 or less slices, don't allocate memory.
                     let mut vec_of_io_slices = uptown_funk::SmallVec::<[std::io::IoSlice; 4]>::with_capacity(io_slices.len());
                     for io_vec_t in io_slices.into_iter() {
-                        let io_slice = state_wrapper.wasm_memory().get(
-                            io_vec_t.ptr as usize..(io_vec_t.ptr + io_vec_t.len) as usize);
+                        let io_slice = state_wrapper.wasm_memory().get(#io_vec_range);
                         let io_slice = uptown_funk::Trap::try_option(io_slice)?;
                         let io_slice = std::io::IoSlice::new(io_slice);
                         vec_of_io_slices.push(io_slice);
@@ -142,18 +159,19 @@ pub fn transform(
         Transformation::RefMutSliceIoSlicesMut => {
             let varname_ptr = format_ident!("{}_ptr_", argument_name);
             let varname_len = format_ident!("{}_len_", argument_name);
-            let input_argument = quote! { #varname_ptr: i32, #varname_len: i32 };
+            let int_ty = ptr_int_type(memory64);
+            let input_argument = quote! { #varname_ptr: #int_ty, #varname_len: #int_ty };
+            let range = checked_memory_range(&quote! { #varname_ptr }, &quote! { #varname_len });
+            let io_vec_range = checked_memory_range(&quote! { io_vec_t.ptr }, &quote! { io_vec_t.len });
             let transformation = quote! {
                 let mut #argument_name = {
-                    let slice = state_wrapper.wasm_memory().get_mut(
-                        #varname_ptr as usize..(#varname_ptr + #varname_len) as usize);
+                    let slice = state_wrapper.wasm_memory().get_mut(#range);
                     let slice = uptown_funk::Trap::try_option(slice)?;
                     let io_slices: &mut [uptown_funk::IoVecT] = unsafe { std::mem::transmute(slice) };
                     // If we only need 4 or less slices, don't allocate memory.
                     let mut vec_of_io_slices = uptown_funk::SmallVec::<[std::io::IoSliceMut; 4]>::with_capacity(io_slices.len());
                     for io_vec_t in io_slices.into_iter() {
-                        let io_slice = state_wrapper.wasm_memory().get_mut(
-                            io_vec_t.ptr as usize..(io_vec_t.ptr + io_vec_t.len) as usize);
+                        let io_slice = state_wrapper.wasm_memory().get_mut(#io_vec_range);
                         let io_slice = uptown_funk::Trap::try_option(io_slice)?;
                         let io_slice_mut = std::io::IoSliceMut::new(io_slice);
                         vec_of_io_slices.push(io_slice_mut);
@@ -164,10 +182,197 @@ pub fn transform(
             let host_call_argument = quote! { #argument_name.as_mut_slice() };
             Ok((input_argument, transformation, host_call_argument))
         }
+        // &[i32], &[u64], &[f32], ...
+        Transformation::NumericSlice(elem) => {
+            Ok(transform_numeric_slice(argument_name, &elem, false, memory64))
+        }
+        // &mut [i32], &mut [u64], ...
+        Transformation::NumericSliceMut(elem) => {
+            Ok(transform_numeric_slice(argument_name, &elem, true, memory64))
+        }
         Transformation::Unsupported => Err(arg_error(&pat_type.ty)),
     }
 }
 
+/// Lowers a `&[T]` / `&mut [T]` argument of a WASM-numeric element type into the
+/// `(ptr, len)` guest pair, where `len` counts elements.
+///
+/// Because a host's pointer alignment and endianness need not match the guest's,
+/// the bytes cannot simply be reinterpreted the way `&mut [u8]` and the IoSlice
+/// cases are. Instead we validate that `ptr` is aligned to `size_of::<T>()` and
+/// that `len * size_of::<T>()` fits in linear memory — via
+/// [`uptown_funk::mem::checked_elem_range`] — and copy the elements into a
+/// `SmallVec` through `from_le_bytes`. For the `&mut` case a drop guard writes
+/// the (possibly mutated) values back as little-endian.
+fn transform_numeric_slice(
+    argument_name: &Ident,
+    elem: &Ident,
+    mutable: bool,
+    memory64: bool,
+) -> (TokenStream2, TokenStream2, TokenStream2) {
+    let varname_ptr = format_ident!("{}_ptr_", argument_name);
+    let varname_len = format_ident!("{}_len_", argument_name);
+    let int_ty = ptr_int_type(memory64);
+    let input_argument = quote! { #varname_ptr: #int_ty, #varname_len: #int_ty };
+
+    // Validate alignment and the `count * size_of::<T>()` length in one checked
+    // step, widening the guest arguments with `i64::from` as the byte-slice
+    // transformations do.
+    let bounds = quote! {
+        let elem_size = std::mem::size_of::<#elem>();
+        let (byte_range, count) = uptown_funk::Trap::try_option(
+            uptown_funk::mem::checked_elem_range(
+                i64::from(#varname_ptr),
+                i64::from(#varname_len),
+                elem_size,
+            )
+        )?;
+    };
+
+    if mutable {
+        let transformation = quote! {
+            let mut #argument_name = {
+                #bounds
+                let bytes: &mut [u8] = uptown_funk::Trap::try_option(
+                    state_wrapper.wasm_memory().get_mut(byte_range))?;
+                // WASM memory is little-endian; decode into host-native values.
+                let mut values = uptown_funk::SmallVec::<[#elem; 8]>::with_capacity(count);
+                for chunk in bytes.chunks_exact(elem_size) {
+                    let mut buf = [0u8; std::mem::size_of::<#elem>()];
+                    buf.copy_from_slice(chunk);
+                    values.push(<#elem>::from_le_bytes(buf));
+                }
+                // Flush the (possibly mutated) values back to the guest on drop.
+                struct WriteBack<'a> {
+                    bytes: &'a mut [u8],
+                    values: uptown_funk::SmallVec<[#elem; 8]>,
+                }
+                impl<'a> std::ops::Deref for WriteBack<'a> {
+                    type Target = [#elem];
+                    fn deref(&self) -> &[#elem] { self.values.as_slice() }
+                }
+                impl<'a> std::ops::DerefMut for WriteBack<'a> {
+                    fn deref_mut(&mut self) -> &mut [#elem] { self.values.as_mut_slice() }
+                }
+                impl<'a> std::ops::Drop for WriteBack<'a> {
+                    fn drop(&mut self) {
+                        let elem_size = std::mem::size_of::<#elem>();
+                        for (chunk, value) in self
+                            .bytes
+                            .chunks_exact_mut(elem_size)
+                            .zip(self.values.iter())
+                        {
+                            chunk.copy_from_slice(&value.to_le_bytes());
+                        }
+                    }
+                }
+                WriteBack { bytes, values }
+            };
+        };
+        let host_call_argument = quote! { &mut #argument_name[..] };
+        (input_argument, transformation, host_call_argument)
+    } else {
+        let transformation = quote! {
+            let #argument_name = {
+                #bounds
+                let bytes: &[u8] = uptown_funk::Trap::try_option(
+                    state_wrapper.wasm_memory().get(byte_range))?;
+                // WASM memory is little-endian; decode into host-native values.
+                let mut values = uptown_funk::SmallVec::<[#elem; 8]>::with_capacity(count);
+                for chunk in bytes.chunks_exact(elem_size) {
+                    let mut buf = [0u8; std::mem::size_of::<#elem>()];
+                    buf.copy_from_slice(chunk);
+                    values.push(<#elem>::from_le_bytes(buf));
+                }
+                values
+            };
+        };
+        let host_call_argument = quote! { #argument_name.as_slice() };
+        (input_argument, transformation, host_call_argument)
+    }
+}
+
+// Returns true for element types that map directly onto WASM-numeric values and
+// expose `from_le_bytes`/`to_le_bytes`.
+fn is_wasm_numeric(ident: &Ident) -> bool {
+    ident == "i32"
+        || ident == "i64"
+        || ident == "f32"
+        || ident == "f64"
+        || ident == "u16"
+        || ident == "u32"
+        || ident == "u64"
+}
+
+/// Turns a guest `(ptr, len)` pair into a `start..end` byte range expression.
+///
+/// The guest passes signed values, so a malicious module can hand us a negative
+/// pointer or a `ptr + len` sum that overflows. The old code computed
+/// `ptr as usize..(ptr + len) as usize` directly, which panics in debug and
+/// silently wraps to a small in-bounds range in release. The checked arithmetic
+/// lives in [`uptown_funk::mem::checked_range`]; both operands are widened with
+/// `i64::from`, which accepts the `i32`/`i64` pointer arguments as well as the
+/// unsigned `u32` ciovec/iovec fields without a lossy cast.
+fn checked_memory_range(ptr: &TokenStream2, len: &TokenStream2) -> TokenStream2 {
+    quote! {
+        uptown_funk::Trap::try_option(
+            uptown_funk::mem::checked_range(i64::from(#ptr), i64::from(#len))
+        )?
+    }
+}
+
+// The guest pointer/length integer type for the selected linear-memory ABI.
+fn ptr_int_type(memory64: bool) -> TokenStream2 {
+    if memory64 {
+        quote! { i64 }
+    } else {
+        quote! { i32 }
+    }
+}
+
+/// Lowers a `&OsStr` / `&Path` argument from the same `(ptr, len)` pair as
+/// `&str`, but decodes the guest bytes as WTF-8 instead of strict UTF-8 so that
+/// non-UTF-8 filesystem names round-trip instead of trapping.
+///
+/// On Unix the bytes are an `OsStr` verbatim, so they are borrowed directly with
+/// no allocation. On other targets the bytes are decoded into an owned
+/// `OsString` via [`uptown_funk::wtf8::decode`]: on Windows that preserves
+/// unpaired surrogates, while on targets that are neither Unix nor Windows
+/// ill-formed sequences, unpaired surrogates included, are replaced with
+/// U+FFFD. The value is kept in a `Cow` so the Unix path stays zero-copy.
+fn transform_os_path(
+    argument_name: &Ident,
+    as_path: bool,
+    memory64: bool,
+) -> (TokenStream2, TokenStream2, TokenStream2) {
+    let varname_ptr = format_ident!("{}_ptr_", argument_name);
+    let varname_len = format_ident!("{}_len_", argument_name);
+    let int_ty = ptr_int_type(memory64);
+    let input_argument = quote! { #varname_ptr: #int_ty, #varname_len: #int_ty };
+    let range = checked_memory_range(&quote! { #varname_ptr }, &quote! { #varname_len });
+    let transformation = quote! {
+        let #argument_name = {
+            let slice = state_wrapper.wasm_memory().get(#range);
+            let slice = uptown_funk::Trap::try_option(slice)?;
+            #[cfg(unix)]
+            let decoded: std::borrow::Cow<std::ffi::OsStr> = {
+                use std::os::unix::ffi::OsStrExt;
+                std::borrow::Cow::Borrowed(std::ffi::OsStr::from_bytes(slice))
+            };
+            #[cfg(not(unix))]
+            let decoded: std::borrow::Cow<std::ffi::OsStr> =
+                std::borrow::Cow::Owned(uptown_funk::wtf8::decode(slice));
+            decoded
+        };
+    };
+    let host_call_argument = if as_path {
+        quote! { std::path::Path::new(&*#argument_name) }
+    } else {
+        quote! { &*#argument_name }
+    };
+    (input_argument, transformation, host_call_argument)
+}
+
 // Transformation for path types i32, CustomType, ...
 fn transform_path(path: &Path) -> Transformation {
     if let Some(ident) = path.get_ident() {
@@ -195,6 +400,9 @@ fn transform_reference(reference: &TypeReference) -> Transformation {
                         // &mut [u8]
                         } else if last_segment.ident == "u8" {
                             Transformation::RefMutSlice
+                        // &mut [i32], &mut [u64], ...
+                        } else if is_wasm_numeric(&last_segment.ident) {
+                            Transformation::NumericSliceMut(last_segment.ident.clone())
                         } else {
                             Transformation::Unsupported
                         }
@@ -210,16 +418,25 @@ fn transform_reference(reference: &TypeReference) -> Transformation {
 
     match &*reference.elem {
         Type::Path(type_path) => {
-            if let Some(ident) = type_path.path.get_ident() {
+            if let Some(segment) = type_path.path.segments.last() {
                 // &str
-                if ident == "str" {
-                    return Transformation::RefStr;
-                // Everything else is considered a &CustomType
+                if segment.ident == "str" {
+                    Transformation::RefStr
+                // &std::ffi::OsStr
+                } else if segment.ident == "OsStr" {
+                    Transformation::RefOsStr
+                // &std::path::Path
+                } else if segment.ident == "Path" {
+                    Transformation::RefPath
+                // A bare single-segment identifier is considered a &CustomType
+                } else if type_path.path.get_ident().is_some() {
+                    Transformation::RefCustomType
                 } else {
-                    return Transformation::RefCustomType;
+                    Transformation::Unsupported
                 }
+            } else {
+                Transformation::Unsupported
             }
-            Transformation::Unsupported
         }
         Type::Slice(type_slice) => match &*type_slice.elem {
             Type::Path(type_path) => {
@@ -227,6 +444,9 @@ fn transform_reference(reference: &TypeReference) -> Transformation {
                     // &[std::io::IoSlice]
                     if last_segment.ident == "IoSlice" {
                         Transformation::RefSliceIoSlices
+                    // &[i32], &[u64], &[f32], ...
+                    } else if is_wasm_numeric(&last_segment.ident) {
+                        Transformation::NumericSlice(last_segment.ident.clone())
                     } else {
                         Transformation::Unsupported
                     }
@@ -245,8 +465,35 @@ enum Transformation {
     CustomType,
     RefCustomType,
     RefStr,
+    // &std::ffi::OsStr — decoded as WTF-8 instead of strict UTF-8.
+    RefOsStr,
+    // &std::path::Path — decoded as WTF-8 instead of strict UTF-8.
+    RefPath,
     RefMutSlice,
     RefSliceIoSlices,
     RefMutSliceIoSlicesMut,
+    // &[i32], &[u64], &[f32], ... — slices of WASM-numeric element types.
+    NumericSlice(Ident),
+    // &mut [i32], &mut [u64], ... — written back on drop.
+    NumericSliceMut(Ident),
     Unsupported,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_wasm_numeric_element_types() {
+        for ty in ["i32", "i64", "f32", "f64", "u16", "u32", "u64"] {
+            assert!(is_wasm_numeric(&format_ident!("{}", ty)), "{} should be numeric", ty);
+        }
+    }
+
+    #[test]
+    fn rejects_non_numeric_element_types() {
+        for ty in ["u8", "i8", "bool", "IoSlice", "String"] {
+            assert!(!is_wasm_numeric(&format_ident!("{}", ty)), "{} should not be numeric", ty);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+mod inputs;
+mod outputs;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::ToTokens;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{FnArg, Ident, ReturnType, Signature, Token};
+
+pub(crate) use inputs::transform;
+pub(crate) use outputs::transform_return;
+
+/// Parses the `#[host_function(...)]` attribute arguments, returning whether the
+/// `memory64` flag was requested.
+///
+/// The flag is threaded into [`transform_signature`] (and from there into
+/// [`transform`]) to select the `i64` guest pointer ABI for input arguments of
+/// modules built against the WebAssembly memory64 proposal. [`transform_return`]
+/// does not take the flag; its out-pointer/out-length pair is always `i32`. The entry point
+/// calls this with the attribute token stream and reports any error via
+/// `to_compile_error`. Unknown flags are rejected.
+pub(crate) fn parse_memory64(attr: TokenStream2) -> Result<bool, syn::Error> {
+    let flags = Punctuated::<Ident, Token![,]>::parse_terminated.parse2(attr)?;
+    let mut memory64 = false;
+    for flag in flags {
+        if flag == "memory64" {
+            memory64 = true;
+        } else {
+            return Err(syn::Error::new_spanned(
+                flag,
+                "unknown host_function flag; expected `memory64`",
+            ));
+        }
+    }
+    Ok(memory64)
+}
+
+/// Lowers an entire host-function signature into the WASM guest ABI.
+///
+/// Every input argument is lowered with [`transform`], which receives the
+/// selected pointer ABI (`memory64`), and the return type with
+/// [`transform_return`], which always uses `i32` pointers. The accumulated fragments are stitched into the generated wrapper by
+/// the `#[host_function]` attribute.
+pub(crate) fn transform_signature(
+    sig: &Signature,
+    memory64: bool,
+) -> Result<SignatureParts, TokenStream> {
+    let mut parts = SignatureParts::default();
+
+    for input in sig.inputs.iter() {
+        let pat_type = match input {
+            // `self` receivers are part of the host state, not the guest ABI.
+            FnArg::Receiver(_) => continue,
+            FnArg::Typed(pat_type) => pat_type,
+        };
+        let (guest_argument, mapping, host_call_argument) = transform(pat_type, memory64)?;
+        parts.guest_inputs.push(guest_argument);
+        parts.input_mappings.push(mapping);
+        parts.host_call_arguments.push(host_call_argument);
+    }
+
+    // `ReturnType::Default` (`fn f()`) is a void function and forwards nothing.
+    if let ReturnType::Type(_, return_type) = &sig.output {
+        let (guest_output, mapping, guest_return) = transform_return(return_type)?;
+        parts.guest_inputs.push(guest_output);
+        parts.return_mapping = mapping;
+        parts.guest_return = guest_return;
+    }
+
+    Ok(parts)
+}
+
+/// The token fragments produced by [`transform_signature`].
+#[derive(Default)]
+pub(crate) struct SignatureParts {
+    pub guest_inputs: Vec<TokenStream2>,
+    pub input_mappings: Vec<TokenStream2>,
+    pub host_call_arguments: Vec<TokenStream2>,
+    pub return_mapping: TokenStream2,
+    pub guest_return: TokenStream2,
+}
+
+/// Builds the "unsupported host function signature type" compile error shared by
+/// the input and return transformations.
+pub(crate) fn arg_error<T: ToTokens>(tokens: &T) -> TokenStream {
+    syn::Error::new_spanned(tokens, "unsupported host function signature type")
+        .to_compile_error()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn no_flags_defaults_to_32_bit() {
+        assert!(!parse_memory64(quote! {}).unwrap());
+    }
+
+    #[test]
+    fn memory64_flag_selects_64_bit() {
+        assert!(parse_memory64(quote! { memory64 }).unwrap());
+    }
+
+    #[test]
+    fn unknown_flag_is_rejected() {
+        assert!(parse_memory64(quote! { memory128 }).is_err());
+    }
+}
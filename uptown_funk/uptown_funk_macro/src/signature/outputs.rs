@@ -0,0 +1,172 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{GenericArgument, Path, PathArguments, Type};
+
+use super::arg_error;
+
+/// Takes the return type of the host function's signature and lowers it into a
+/// WASI-style ABI, the counterpart to [`super::inputs::transform`] for inputs.
+///
+/// As with the argument transformation there are 3 parts (the return values):
+/// 1. Extra input arguments appended to the WASM guest function's signature
+///    (an out-pointer/out-length pair for owned buffers, empty otherwise).
+/// 2. Code that maps the host return value (bound as `ret`) to the guest ABI.
+/// 3. The value returned to the guest.
+///
+/// The following rules are followed when doing the transformation:
+/// 1. **i32, i64, f32 and f64** (WASM guest compatible types) are forwarded unchanged.
+/// 2. **Owned String / Vec<u8>** are copied into a caller-supplied out-pointer/out-length pair that is
+///    appended to the guest signature; the call traps if the guest buffer is too small and otherwise
+///    returns the number of bytes written.
+/// 3. **Custom types** need to implement uptown_funk::ToWasmI32 and are serialized into an **i32** handle.
+/// 4. All other patterns will result in a compilation error.
+pub fn transform_return(
+    return_type: &Type,
+) -> Result<(TokenStream2, TokenStream2, TokenStream2), TokenStream> {
+    match classify(return_type) {
+        // i32, i64, f32, f64
+        ReturnTransformation::None => Ok((quote! {}, quote! {}, quote! { ret })),
+        // CustomStruct, CustomEnum, ...
+        ReturnTransformation::CustomType => {
+            let transformation = quote! {
+                let ret = <#return_type as uptown_funk::ToWasmI32>::to_i32(
+                    state_wrapper.state(),
+                    state_wrapper.instance_environment(),
+                    ret
+                );
+            };
+            Ok((quote! {}, transformation, quote! { ret }))
+        }
+        // String
+        ReturnTransformation::OwnedString => {
+            Ok(owned_bytes(quote! { ret.as_bytes() }))
+        }
+        // Vec<u8>
+        ReturnTransformation::OwnedByteVec => {
+            Ok(owned_bytes(quote! { ret.as_slice() }))
+        }
+        ReturnTransformation::Unsupported => Err(arg_error(return_type)),
+    }
+}
+
+/// Shared lowering for owned byte buffers (`String`, `Vec<u8>`). The guest passes
+/// an out-pointer and the capacity of its buffer; we bounds-check that pair the
+/// same way the argument transformations do, trap when the value does not fit,
+/// and otherwise return the number of bytes written.
+fn owned_bytes(bytes: TokenStream2) -> (TokenStream2, TokenStream2, TokenStream2) {
+    let input_argument = quote! { ret_out_ptr_: i32, ret_out_len_: i32 };
+    let transformation = quote! {
+        let ret = {
+            let bytes = #bytes;
+            let range = uptown_funk::Trap::try_option(
+                uptown_funk::mem::checked_range(i64::from(ret_out_ptr_), i64::from(ret_out_len_))
+            )?;
+            let out = uptown_funk::Trap::try_option(state_wrapper.wasm_memory().get_mut(range))?;
+            // Traps if the guest buffer is too small; otherwise returns the
+            // number of bytes written. The length is bounds-checked before the
+            // `i32` conversion so a >= 2 GiB value cannot wrap to a negative.
+            let written = uptown_funk::Trap::try_option(uptown_funk::mem::copy_out(out, bytes))?;
+            uptown_funk::Trap::try_result(<i32 as std::convert::TryFrom<usize>>::try_from(written))?
+        };
+    };
+    (input_argument, transformation, quote! { ret })
+}
+
+// Classifies the return type into one of the supported lowerings.
+fn classify(return_type: &Type) -> ReturnTransformation {
+    match return_type {
+        // `-> ()` is a void function and forwards nothing, like `ReturnType::Default`.
+        Type::Tuple(tuple) if tuple.elems.is_empty() => ReturnTransformation::None,
+        Type::Path(type_path) => {
+            if is_byte_vec(&type_path.path) {
+                return ReturnTransformation::OwnedByteVec;
+            }
+            // Match by the last path segment so fully-qualified spellings such as
+            // `std::string::String` are recognized, not just the bare ident.
+            match type_path.path.segments.last() {
+                Some(segment)
+                    if segment.ident == "i32"
+                        || segment.ident == "i64"
+                        || segment.ident == "f32"
+                        || segment.ident == "f64" =>
+                {
+                    ReturnTransformation::None
+                }
+                Some(segment) if segment.ident == "String" => ReturnTransformation::OwnedString,
+                Some(_) => ReturnTransformation::CustomType,
+                None => ReturnTransformation::Unsupported,
+            }
+        }
+        _ => ReturnTransformation::Unsupported,
+    }
+}
+
+// Returns true for a `Vec<u8>` path, ignoring any leading path segments.
+fn is_byte_vec(path: &Path) -> bool {
+    let segment = match path.segments.last() {
+        Some(segment) => segment,
+        None => return false,
+    };
+    if segment.ident != "Vec" {
+        return false;
+    }
+    if let PathArguments::AngleBracketed(args) = &segment.arguments {
+        if let Some(GenericArgument::Type(Type::Path(type_path))) = args.args.first() {
+            return type_path.path.get_ident().map_or(false, |ident| ident == "u8");
+        }
+    }
+    false
+}
+
+enum ReturnTransformation {
+    None,
+    CustomType,
+    OwnedString,
+    OwnedByteVec,
+    Unsupported,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify_str(src: &str) -> ReturnTransformation {
+        classify(&syn::parse_str::<Type>(src).unwrap())
+    }
+
+    #[test]
+    fn scalars_are_forwarded() {
+        assert!(matches!(classify_str("i32"), ReturnTransformation::None));
+        assert!(matches!(classify_str("f64"), ReturnTransformation::None));
+    }
+
+    #[test]
+    fn unit_is_a_void_forward() {
+        assert!(matches!(classify_str("()"), ReturnTransformation::None));
+    }
+
+    #[test]
+    fn string_is_matched_by_last_segment() {
+        assert!(matches!(classify_str("String"), ReturnTransformation::OwnedString));
+        assert!(matches!(
+            classify_str("std::string::String"),
+            ReturnTransformation::OwnedString
+        ));
+    }
+
+    #[test]
+    fn byte_vec_is_matched_regardless_of_qualification() {
+        assert!(matches!(classify_str("Vec<u8>"), ReturnTransformation::OwnedByteVec));
+        assert!(matches!(
+            classify_str("std::vec::Vec<u8>"),
+            ReturnTransformation::OwnedByteVec
+        ));
+        assert!(matches!(classify_str("Vec<u32>"), ReturnTransformation::CustomType));
+    }
+
+    #[test]
+    fn other_named_types_are_custom() {
+        assert!(matches!(classify_str("Handle"), ReturnTransformation::CustomType));
+    }
+}